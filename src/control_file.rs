@@ -135,6 +135,129 @@ pub trait FieldName {
     const NAME: &'static str;
 }
 
+/// A single `Name: value` field, with the value including any continuation
+/// lines and their leading whitespace, exactly as found in the source.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Field<'a> {
+    pub field_name: &'a str,
+    pub value: &'a str,
+}
+
+/// Parses one field, regardless of name, into a [`Field`].
+pub fn field(input: &str) -> IResult<&str, Field<'_>> {
+    map(
+        separated_pair(
+            field_name,
+            space0,
+            recognize(pair(rest_of_line, many0(continuation_line))),
+        ),
+        |(field_name, value)| Field { field_name, value },
+    )(input)
+}
+
+/// Spanned variants of the parsers above, for callers that need to report
+/// *where* a parse failure occurred rather than just that one occurred.
+///
+/// These parse the same grammar as [`field`] and [`clean_multiline`], but
+/// return `nom::error::VerboseError` so that the `context(...)` labels added
+/// here (`"field"`, `"multiline field"`) and by callers layered on top
+/// survive, and resolve every recognized sub-slice back to a
+/// [`crate::span::Span`] via a [`crate::span::SourceMap`] built over the
+/// whole input. The underlying parsers report plain `nom::error::Error`s, so
+/// a failure inside them becomes a single `VerboseErrorKind::Nom` entry; the
+/// `"field name"` label on [`field_name`] is not carried over.
+pub mod spanned {
+    use nom::{
+        character::complete::space0,
+        combinator::{map, recognize},
+        error::{context, VerboseError},
+        multi::many0,
+        sequence::{pair, separated_pair},
+        IResult,
+    };
+
+    use crate::span::{SourceMap, Span};
+
+    use super::{continuation_line, field_name, rest_of_line, Field};
+
+    type VResult<'a, O> = IResult<&'a str, O, VerboseError<&'a str>>;
+
+    fn span_of(map: &SourceMap<'_>, sub: &str) -> Span {
+        map.span_of(sub)
+            .expect("parser output must be a sub-slice of the source map's buffer")
+    }
+
+    /// A [`Field`] together with the spans of its name and its value.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SpannedField<'a> {
+        pub field: Field<'a>,
+        pub name_span: Span,
+        pub value_span: Span,
+    }
+
+    /// Parses one field and resolves its name/value spans against `source_map`.
+    pub fn field<'a>(
+        source_map: &'a SourceMap<'a>,
+    ) -> impl FnMut(&'a str) -> VResult<'a, SpannedField<'a>> {
+        move |input| {
+            context(
+                "field",
+                map(
+                    |input| {
+                        separated_pair(
+                            field_name,
+                            space0,
+                            recognize(pair(rest_of_line, many0(continuation_line))),
+                        )(input)
+                        .map_err(|e| {
+                            e.map(|nom::error::Error { input, code }| VerboseError {
+                                errors: vec![(input, nom::error::VerboseErrorKind::Nom(code))],
+                            })
+                        })
+                    },
+                    |(field_name, value): (&str, &str)| SpannedField {
+                        field: Field { field_name, value },
+                        name_span: span_of(source_map, field_name),
+                        value_span: span_of(source_map, value),
+                    },
+                ),
+            )(input)
+        }
+    }
+
+    /// A cleaned multi-line value together with the span of each cleaned line.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SpannedLines<'a> {
+        pub lines: Vec<&'a str>,
+        pub spans: Vec<Span>,
+    }
+
+    /// Cleans a multi-line string like [`clean_multiline`], additionally
+    /// resolving the span of each returned line against `source_map`.
+    pub fn clean_multiline<'a>(
+        source_map: &'a SourceMap<'a>,
+    ) -> impl FnMut(&'a str) -> VResult<'a, SpannedLines<'a>> {
+        move |input| {
+            context(
+                "multiline field",
+                map(
+                    |input| {
+                        super::clean_multiline(input).map_err(|e| {
+                            e.map(|nom::error::Error { input, code }| VerboseError {
+                                errors: vec![(input, nom::error::VerboseErrorKind::Nom(code))],
+                            })
+                        })
+                    },
+                    |lines: Vec<&str>| {
+                        let spans = lines.iter().map(|line| span_of(source_map, line)).collect();
+                        SpannedLines { lines, spans }
+                    },
+                ),
+            )(input)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use nom::combinator::all_consuming;
@@ -232,4 +355,21 @@ baz: baz
         assert_eq!(o, vec!["0\n", "a\n", "\n", " b"]);
         assert!(i.is_empty());
     }
+
+    #[test]
+    fn test_spanned_field() {
+        use super::spanned::field;
+        use crate::span::{SourceMap, Span};
+
+        let input = "Format: https://example.com/\nFiles: *\n";
+        let map = SourceMap::new(input);
+        let (_i, o) = field(&map)(input).expect("have a field");
+        assert_eq!(o.field.field_name, "Format");
+        assert_eq!(o.name_span, Span::new(0, 6));
+        let value_start = input.find("https").expect("url present");
+        assert_eq!(
+            o.value_span,
+            Span::new(value_start, value_start + o.field.value.len())
+        );
+    }
 }
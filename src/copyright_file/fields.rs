@@ -2,9 +2,18 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use nom::{combinator::map, multi::many1, IResult};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while_m_n},
+    character::complete::{char, space0},
+    combinator::{map, map_res, opt},
+    multi::{many1, separated_list1},
+    sequence::separated_pair,
+    IResult,
+};
 
 use crate::control_file::{multi_line_field, named_single_line_field, FieldName};
+use crate::error::CopyrightError;
 
 pub trait ParseField: Sized {
     fn parse(input: &str) -> IResult<&str, Self>;
@@ -81,19 +90,280 @@ impl ParseField for Comment {
     }
 }
 
+/// A boolean expression over the `and`/`or` connectives DEP-5 allows in a
+/// `License` field's short name, e.g. `GPL-2.0+ and BSD-3-Clause`. Distinct
+/// from [`crate::spdx::LicenseExpr`]: this is DEP-5's own, simpler grammar
+/// (lowercase connectives, no `WITH`/`+` handling), not a full SPDX
+/// expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseNameExpr {
+    Id(String),
+    And(Box<LicenseNameExpr>, Box<LicenseNameExpr>),
+    Or(Box<LicenseNameExpr>, Box<LicenseNameExpr>),
+}
+
+impl LicenseNameExpr {
+    /// Collects every distinct license identifier referenced by this
+    /// expression, in the order they first appear.
+    pub fn license_ids(&self) -> Vec<&str> {
+        let mut ids = Vec::new();
+        self.collect_ids(&mut ids);
+        ids
+    }
+
+    fn collect_ids<'a>(&'a self, ids: &mut Vec<&'a str>) {
+        match self {
+            LicenseNameExpr::Id(id) => {
+                if !ids.contains(&id.as_str()) {
+                    ids.push(id.as_str());
+                }
+            }
+            LicenseNameExpr::And(lhs, rhs) | LicenseNameExpr::Or(lhs, rhs) => {
+                lhs.collect_ids(ids);
+                rhs.collect_ids(ids);
+            }
+        }
+    }
+}
+
+fn license_name_id(input: &str) -> IResult<&str, LicenseNameExpr> {
+    map(
+        nom::bytes::complete::take_while1(|c: char| {
+            c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+')
+        }),
+        |s: &str| LicenseNameExpr::Id(s.to_string()),
+    )(input)
+}
+
+/// `license_name_id`s joined by `and`, left-associative; binds tighter than `or`.
+fn license_name_and(input: &str) -> IResult<&str, LicenseNameExpr> {
+    let (input, first) = license_name_id(input)?;
+    nom::multi::fold_many0(
+        nom::sequence::preceded(
+            nom::sequence::tuple((
+                nom::character::complete::space1,
+                tag("and"),
+                nom::character::complete::space1,
+            )),
+            license_name_id,
+        ),
+        move || first.clone(),
+        |acc, next| LicenseNameExpr::And(Box::new(acc), Box::new(next)),
+    )(input)
+}
+
+/// Parses a DEP-5 `License` short name into a [`LicenseNameExpr`].
+pub fn license_name_expr(input: &str) -> IResult<&str, LicenseNameExpr> {
+    let (input, first) = license_name_and(input)?;
+    nom::multi::fold_many0(
+        nom::sequence::preceded(
+            nom::sequence::tuple((
+                nom::character::complete::space1,
+                tag("or"),
+                nom::character::complete::space1,
+            )),
+            license_name_and,
+        ),
+        move || first.clone(),
+        |acc, next| LicenseNameExpr::Or(Box::new(acc), Box::new(next)),
+    )(input)
+}
+
+/// A DEP-5 `License` field, split into its short name (possibly an `and`/`or`
+/// expression over SPDX-style identifiers) and any full license text given
+/// on continuation lines.
 #[derive(Debug, Clone, PartialEq)]
-pub struct License(pub String);
+pub struct License {
+    pub name: String,
+    pub text: Option<String>,
+}
 impl FieldName for License {
     const NAME: &'static str = "License";
 }
 impl ParseField for License {
     fn parse(input: &str) -> IResult<&str, Self> {
-        map(multi_line_field::<Self>, |v| Self(v.to_owned()))(input)
+        map(multi_line_field::<Self>, |raw: &str| {
+            let mut lines = raw.lines();
+            let name = lines.next().unwrap_or("").trim().to_string();
+            let text_lines: Vec<&str> = lines
+                .map(|line| line.strip_prefix(' ').unwrap_or(line))
+                .collect();
+            // `str::lines()` drops the source's trailing line ending, so
+            // re-attach it here to keep the split lossless: `text` should
+            // round-trip back into the same continuation lines `raw` had.
+            let text = (!text_lines.is_empty()).then(|| {
+                let mut joined = text_lines.join("\n");
+                if raw.ends_with('\n') {
+                    joined.push('\n');
+                }
+                joined
+            });
+            Self { name, text }
+        })(input)
+    }
+}
+impl License {
+    /// Parses [`Self::name`] into a [`LicenseNameExpr`], exposing the set of
+    /// referenced license identifiers.
+    pub fn name_expr(&self) -> IResult<&str, LicenseNameExpr> {
+        license_name_expr(self.name.trim())
+    }
+
+    /// Parses [`Self::name`] as an SPDX license expression (`Apache-2.0 OR
+    /// MIT`, `LGPL-2.1-only WITH exception`, ...), for callers that want the
+    /// full SPDX grammar rather than DEP-5's simpler `and`/`or` form.
+    pub fn expression(&self) -> IResult<&str, crate::spdx::LicenseExpr> {
+        crate::spdx::license_expr(self.name.trim())
+    }
+
+    /// Expands a bare `License { text: None, .. }` into one carrying the
+    /// bundled canonical text for [`Self::name`], with `year`/`holder`
+    /// substituted in. Leaves an already-populated `text` untouched, and
+    /// leaves `text: None` if [`Self::name`] has no bundled template.
+    pub fn with_generated_text(&self, year: &str, holder: &str) -> Self {
+        if self.text.is_some() {
+            return self.clone();
+        }
+        Self {
+            name: self.name.clone(),
+            text: crate::license_text::render(&self.name, year, holder),
+        }
+    }
+
+    /// Like [`ParseField::parse`], but maps a parse failure onto a
+    /// [`CopyrightError`] and additionally rejects an empty license name,
+    /// for callers that want an actionable diagnostic instead of a bare nom
+    /// error.
+    pub fn parse_checked(input: &str) -> Result<Self, CopyrightError> {
+        let (_rest, license) = Self::parse(input).map_err(|_| CopyrightError::MalformedContinuationLine {
+            field: Self::NAME,
+            line: input.lines().next().unwrap_or(input).to_string(),
+        })?;
+        if license.name.is_empty() {
+            return Err(CopyrightError::EmptyField { field: Self::NAME });
+        }
+        Ok(license)
     }
 }
 
+/// A single parsed `Copyright:` line: either a recognized notice, or the
+/// verbatim original text if it didn't match the recognized grammar, so
+/// parsing a `Copyright` field is always lossless.
 #[derive(Debug, Clone, PartialEq)]
-pub struct Copyright(pub Vec<String>);
+pub enum CopyrightLine {
+    Notice(CopyrightNotice),
+    Raw(String),
+}
+
+/// A copyright notice split into its holder and the year(s) it covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyrightNotice {
+    pub holders: String,
+    pub first_year: u16,
+    /// Present when the notice is written as a range (`2018-2021`, even one
+    /// whose ends are equal) or as a list of more than one distinct year
+    /// (`2018, 2019, 2021`).
+    pub last_year: Option<u16>,
+}
+
+impl CopyrightNotice {
+    fn render(&self) -> String {
+        let years = match self.last_year {
+            Some(last) if last != self.first_year => format!("{}-{last}", self.first_year),
+            _ => self.first_year.to_string(),
+        };
+        if self.holders.is_empty() {
+            years
+        } else {
+            format!("{years} {}", self.holders)
+        }
+    }
+}
+
+impl CopyrightLine {
+    /// Renders this line back out as it would appear on a `Copyright:` field.
+    pub fn render(&self) -> String {
+        match self {
+            CopyrightLine::Notice(notice) => notice.render(),
+            CopyrightLine::Raw(line) => line.clone(),
+        }
+    }
+}
+
+fn copyright_prefix(input: &str) -> IResult<&str, &str> {
+    alt((
+        tag("Copyright ©"),
+        tag("Copyright (c)"),
+        tag("Copyright (C)"),
+        tag("©"),
+    ))(input)
+}
+
+fn year(input: &str) -> IResult<&str, u16> {
+    map_res(take_while_m_n(4, 4, |c: char| c.is_ascii_digit()), |s: &str| {
+        s.parse::<u16>()
+    })(input)
+}
+
+/// A year range (`2018-2021`, keeping the part after the dash as the last
+/// year) or a comma/space separated list of years (the smallest becomes
+/// `first_year`, the largest `last_year`).
+fn year_spec(input: &str) -> IResult<&str, (u16, Option<u16>)> {
+    alt((
+        map(separated_pair(year, char('-'), year), |(first, last)| {
+            // Normalize a reversed range (`2021-2018`) so `first_year <=
+            // last_year` holds the same way it already does for the list form.
+            (first.min(last), Some(first.max(last)))
+        }),
+        map(
+            separated_list1(alt((tag(", "), tag(","), tag(" "))), year),
+            |years: Vec<u16>| {
+                let first_year = *years.iter().min().expect("separated_list1 is non-empty");
+                let last_year = years.iter().max().copied().filter(|last| *last != first_year);
+                (first_year, last_year)
+            },
+        ),
+    ))(input)
+}
+
+/// Parses a single copyright line's common prefixes (`©`, `Copyright ©`,
+/// `Copyright (c)`, `Copyright (C)`), year spec, and holder.
+fn copyright_notice(input: &str) -> IResult<&str, CopyrightNotice> {
+    let (rest, _) = opt(copyright_prefix)(input)?;
+    let (rest, _) = space0(rest)?;
+    let (rest, (first_year, last_year)) = year_spec(rest)?;
+    let holders = rest.trim_start_matches([',', ' ']).trim().to_string();
+    Ok(("", CopyrightNotice { holders, first_year, last_year }))
+}
+
+/// Parses one `Copyright:` line into a [`CopyrightLine`], falling back to
+/// [`CopyrightLine::Raw`] if it doesn't match the recognized grammar.
+pub fn parse_copyright_line(line: &str) -> CopyrightLine {
+    match copyright_notice(line) {
+        Ok((_, notice)) => CopyrightLine::Notice(notice),
+        Err(_) => CopyrightLine::Raw(line.to_string()),
+    }
+}
+
+/// Like [`parse_copyright_line`], but surfaces a [`CopyrightError`]
+/// describing why the line doesn't parse as a notice, instead of silently
+/// falling back to [`CopyrightLine::Raw`]. Intended for a linter that wants
+/// to flag lines [`parse_copyright_line`] otherwise swallows.
+pub fn parse_copyright_line_checked(line: &str) -> Result<CopyrightNotice, CopyrightError> {
+    let (rest, _) = opt(copyright_prefix)(line).expect("opt never fails");
+    let rest = rest.trim_start();
+    let (rest, (first_year, last_year)) = year_spec(rest).map_err(|_: nom::Err<nom::error::Error<&str>>| {
+        CopyrightError::UnrecognizedCopyrightPrefix { line: line.to_string() }
+    })?;
+    let holders = rest.trim_start_matches([',', ' ']).trim().to_string();
+    if holders.is_empty() {
+        return Err(CopyrightError::MissingHolderAfterYearRange { line: line.to_string() });
+    }
+    Ok(CopyrightNotice { holders, first_year, last_year })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Copyright(pub Vec<CopyrightLine>);
 impl FieldName for Copyright {
     const NAME: &'static str = "Copyright";
 }
@@ -104,6 +374,61 @@ impl FieldName for Files {
     const NAME: &'static str = "Files";
 }
 
+/// One token of a tokenized `Files:` glob pattern.
+enum GlobToken {
+    Literal(char),
+    /// `*`: matches any sequence of characters, including path separators.
+    Star,
+    /// `?`: matches exactly one character.
+    Question,
+}
+
+/// Splits a `Files:` glob pattern into [`GlobToken`]s, resolving `\`-escapes
+/// (so `\*`/`\?`/`\\` match themselves literally).
+fn tokenize_glob(pattern: &str) -> Vec<GlobToken> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    tokens.push(GlobToken::Literal(escaped));
+                }
+            }
+            '*' => tokens.push(GlobToken::Star),
+            '?' => tokens.push(GlobToken::Question),
+            other => tokens.push(GlobToken::Literal(other)),
+        }
+    }
+    tokens
+}
+
+fn glob_match(tokens: &[GlobToken], path: &[char]) -> bool {
+    match tokens {
+        [] => path.is_empty(),
+        [GlobToken::Star, rest @ ..] => {
+            glob_match(rest, path) || (!path.is_empty() && glob_match(tokens, &path[1..]))
+        }
+        [GlobToken::Question, rest @ ..] => !path.is_empty() && glob_match(rest, &path[1..]),
+        [GlobToken::Literal(c), rest @ ..] => {
+            !path.is_empty() && path[0] == *c && glob_match(rest, &path[1..])
+        }
+    }
+}
+
+impl Files {
+    /// Does `path` (relative to the source root) match any of this
+    /// paragraph's glob patterns? `*` matches any sequence of characters,
+    /// including `/`; `?` matches exactly one character; `\` escapes the
+    /// next character.
+    pub fn matches(&self, path: &str) -> bool {
+        let path: Vec<char> = path.chars().collect();
+        self.0
+            .iter()
+            .any(|pattern| glob_match(&tokenize_glob(pattern), &path))
+    }
+}
+
 impl<T: SingleLineField + From<String>> ParseField for T {
     fn parse(input: &str) -> IResult<&str, Self> {
         map(named_single_line_field(T::NAME), |v| v.to_string().into())(input)
@@ -121,7 +446,9 @@ fn parse_field_with_trimmed_list<T: FieldName>(input: &str) -> IResult<&str, Vec
 }
 impl ParseField for Copyright {
     fn parse(input: &str) -> IResult<&str, Self> {
-        map(parse_field_with_trimmed_list::<Self>, |v| Self(v))(input)
+        map(parse_field_with_trimmed_list::<Self>, |lines| {
+            Self(lines.iter().map(|line| parse_copyright_line(line)).collect())
+        })(input)
     }
 }
 impl ParseField for Files {
@@ -146,4 +473,190 @@ mod tests {
             Format("http://www.debian.org/doc/packaging-manuals/copyright-format/1.0/".to_string())
         )
     }
+
+    #[test]
+    fn test_copyright_line_single_year() {
+        use super::{parse_copyright_line, CopyrightLine, CopyrightNotice};
+        assert_eq!(
+            parse_copyright_line("Copyright (c) 2021 Collabora, Ltd."),
+            CopyrightLine::Notice(CopyrightNotice {
+                holders: "Collabora, Ltd.".to_string(),
+                first_year: 2021,
+                last_year: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_copyright_line_range() {
+        use super::{parse_copyright_line, CopyrightLine, CopyrightNotice};
+        assert_eq!(
+            parse_copyright_line("2018-2021, Collabora, Ltd."),
+            CopyrightLine::Notice(CopyrightNotice {
+                holders: "Collabora, Ltd.".to_string(),
+                first_year: 2018,
+                last_year: Some(2021),
+            })
+        );
+    }
+
+    #[test]
+    fn test_copyright_line_reversed_range_is_normalized() {
+        use super::{parse_copyright_line, CopyrightLine, CopyrightNotice};
+        assert_eq!(
+            parse_copyright_line("2021-2018, Collabora, Ltd."),
+            CopyrightLine::Notice(CopyrightNotice {
+                holders: "Collabora, Ltd.".to_string(),
+                first_year: 2018,
+                last_year: Some(2021),
+            })
+        );
+    }
+
+    #[test]
+    fn test_copyright_line_year_list() {
+        use super::{parse_copyright_line, CopyrightLine, CopyrightNotice};
+        assert_eq!(
+            parse_copyright_line("© 2018, 2019, 2021 Collabora, Ltd."),
+            CopyrightLine::Notice(CopyrightNotice {
+                holders: "Collabora, Ltd.".to_string(),
+                first_year: 2018,
+                last_year: Some(2021),
+            })
+        );
+    }
+
+    #[test]
+    fn test_copyright_line_raw_fallback() {
+        use super::{parse_copyright_line, CopyrightLine};
+        let line = "see individual files for copyright holders";
+        assert_eq!(parse_copyright_line(line), CopyrightLine::Raw(line.to_string()));
+    }
+
+    #[test]
+    fn test_parse_copyright_line_checked_missing_holder() {
+        use super::parse_copyright_line_checked;
+        use crate::error::CopyrightError;
+
+        assert_eq!(
+            parse_copyright_line_checked("2021"),
+            Err(CopyrightError::MissingHolderAfterYearRange { line: "2021".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_parse_copyright_line_checked_unrecognized_prefix() {
+        use super::parse_copyright_line_checked;
+        use crate::error::CopyrightError;
+
+        let line = "see individual files for copyright holders";
+        assert_eq!(
+            parse_copyright_line_checked(line),
+            Err(CopyrightError::UnrecognizedCopyrightPrefix { line: line.to_string() })
+        );
+    }
+
+    #[test]
+    fn test_parse_copyright_line_checked_valid() {
+        use super::{parse_copyright_line_checked, CopyrightNotice};
+
+        assert_eq!(
+            parse_copyright_line_checked("Copyright (c) 2021 Collabora, Ltd."),
+            Ok(CopyrightNotice {
+                holders: "Collabora, Ltd.".to_string(),
+                first_year: 2021,
+                last_year: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_license_parse_checked_rejects_empty_name() {
+        use super::License;
+        use crate::error::CopyrightError;
+
+        assert_eq!(
+            License::parse_checked("License: \n"),
+            Err(CopyrightError::EmptyField { field: "License" })
+        );
+    }
+
+    #[test]
+    fn test_license_splits_name_and_text() {
+        use super::{License, ParseField};
+        let (_i, o) = License::parse("License: MIT\n Permission is hereby granted...\n")
+            .expect("this is valid");
+        assert_eq!(o.name, "MIT");
+        assert_eq!(o.text.as_deref(), Some("Permission is hereby granted...\n"));
+    }
+
+    #[test]
+    fn test_license_with_generated_text() {
+        use super::License;
+
+        let bare = License { name: "MIT".to_string(), text: None };
+        let expanded = bare.with_generated_text("2021", "Collabora, Ltd.");
+        assert!(expanded.text.unwrap().contains("Permission is hereby granted"));
+
+        let already_has_text = License {
+            name: "MIT".to_string(),
+            text: Some("custom text".to_string()),
+        };
+        assert_eq!(
+            already_has_text.with_generated_text("2021", "Collabora, Ltd.").text.as_deref(),
+            Some("custom text")
+        );
+
+        let unknown = License { name: "Nonexistent-License".to_string(), text: None };
+        assert_eq!(unknown.with_generated_text("2021", "Someone").text, None);
+    }
+
+    #[test]
+    fn test_license_name_expr() {
+        use super::LicenseNameExpr;
+        let (_i, o) = super::license_name_expr("GPL-2.0+ and BSD-3-Clause")
+            .expect("this is valid");
+        assert_eq!(
+            o,
+            LicenseNameExpr::And(
+                Box::new(LicenseNameExpr::Id("GPL-2.0+".to_string())),
+                Box::new(LicenseNameExpr::Id("BSD-3-Clause".to_string()))
+            )
+        );
+        assert_eq!(o.license_ids(), vec!["GPL-2.0+", "BSD-3-Clause"]);
+    }
+
+    #[test]
+    fn test_license_expression() {
+        use super::License;
+        use crate::spdx::LicenseExpr;
+        let license = License { name: "Apache-2.0 OR MIT".to_string(), text: None };
+        let (_i, o) = license.expression().expect("this is valid SPDX");
+        assert_eq!(
+            o,
+            LicenseExpr::Or(
+                Box::new(LicenseExpr::Id("Apache-2.0".to_string())),
+                Box::new(LicenseExpr::Id("MIT".to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_files_glob_star_crosses_separators() {
+        use super::Files;
+        let files = Files(vec!["doc/changes/*".to_string()]);
+        assert!(files.matches("doc/changes/foo.md"));
+        assert!(files.matches("doc/changes/sub/dir/foo.md"));
+        assert!(!files.matches("doc/other/foo.md"));
+    }
+
+    #[test]
+    fn test_files_glob_question_and_escape() {
+        use super::Files;
+        let files = Files(vec!["src/foo?.rs".to_string(), r"weird\*name".to_string()]);
+        assert!(files.matches("src/fooX.rs"));
+        assert!(!files.matches("src/fooXY.rs"));
+        assert!(files.matches("weird*name"));
+        assert!(!files.matches("weirdXname"));
+    }
 }
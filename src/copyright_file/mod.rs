@@ -3,11 +3,12 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 pub mod fields;
+pub mod paragraph;
 
 use nom::{
-    branch::{alt, permutation},
-    character::complete::{line_ending, space0, space1},
-    combinator::{map, map_parser, opt},
+    branch::alt,
+    character::complete::{line_ending, space0},
+    combinator::{map, map_opt, map_parser},
     multi::many0,
     sequence::{delimited, pair, preceded},
     IResult,
@@ -16,9 +17,9 @@ use nom::{
 use crate::control_file::{cleaned_multiline, named_multi_line_field};
 
 use self::fields::{
-    Comment, Copyright, Disclaimer, Files, Format, License, ParseField, Source, UpstreamContact,
-    UpstreamName,
+    Comment, Copyright, Disclaimer, Files, Format, License, Source, UpstreamContact, UpstreamName,
 };
+use self::paragraph::paragraph as generic_paragraph;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct HeaderParagraph {
@@ -32,38 +33,23 @@ pub struct HeaderParagraph {
     pub copyright: Option<Copyright>,
 }
 
+/// Parses the leading header paragraph. Built on the generic
+/// [`paragraph::Paragraph`] model rather than `permutation`, so unknown or
+/// reordered fields survive alongside the known ones instead of tripping up
+/// the parse.
 pub fn header_paragraph(input: &str) -> IResult<&str, HeaderParagraph> {
-    map(
-        permutation((
-            Format::parse,
-            opt(UpstreamName::parse),
-            opt(UpstreamContact::parse),
-            opt(Source::parse),
-            opt(Disclaimer::parse),
-            opt(Comment::parse),
-            opt(License::parse),
-            opt(Copyright::parse),
-        )),
-        |(
-            format,
-            upstream_name,
-            upstream_contact,
-            source,
-            disclaimer,
-            comment,
-            license,
-            copyright,
-        )| HeaderParagraph {
-            format,
-            upstream_name,
-            upstream_contact,
-            source,
-            disclaimer,
-            comment,
-            license,
-            copyright,
-        },
-    )(input)
+    map_opt(generic_paragraph, |p| {
+        Some(HeaderParagraph {
+            format: p.extract::<Format>()?,
+            upstream_name: p.extract::<UpstreamName>(),
+            upstream_contact: p.extract::<UpstreamContact>(),
+            source: p.extract::<Source>(),
+            disclaimer: p.extract::<Disclaimer>(),
+            comment: p.extract::<Comment>(),
+            license: p.extract::<License>(),
+            copyright: p.extract::<Copyright>(),
+        })
+    })(input)
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -74,21 +60,17 @@ pub struct FilesParagraph {
     pub comment: Option<Comment>,
 }
 
+/// Parses a `Files:` paragraph, layered on the generic
+/// [`paragraph::Paragraph`] model in the same way as [`header_paragraph`].
 pub fn files_paragraph(input: &str) -> IResult<&str, FilesParagraph> {
-    map(
-        permutation((
-            Files::parse,
-            Copyright::parse,
-            License::parse,
-            opt(Comment::parse),
-        )),
-        |(files, copyright, license, comment)| FilesParagraph {
-            files,
-            copyright,
-            license,
-            comment,
-        },
-    )(input)
+    map_opt(generic_paragraph, |p| {
+        Some(FilesParagraph {
+            files: p.extract::<Files>()?,
+            copyright: p.extract::<Copyright>()?,
+            license: p.extract::<License>()?,
+            comment: p.extract::<Comment>(),
+        })
+    })(input)
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -97,6 +79,13 @@ pub struct LicenseDetailParagraph {
     pub text: String,
 }
 
+impl LicenseDetailParagraph {
+    /// Parses `name` as an SPDX license expression.
+    pub fn expression(&self) -> IResult<&str, crate::spdx::LicenseExpr> {
+        crate::spdx::license_expr(self.name.trim())
+    }
+}
+
 pub fn license_detail_paragraph(input: &str) -> IResult<&str, LicenseDetailParagraph> {
     map(
         map_parser(named_multi_line_field("License"), cleaned_multiline),
@@ -112,6 +101,7 @@ pub fn license_detail_paragraph(input: &str) -> IResult<&str, LicenseDetailParag
     )(input)
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum BodyParagraph {
     Files(FilesParagraph),
     LicenseDetail(LicenseDetailParagraph),
@@ -129,6 +119,7 @@ pub fn body_paragraph(input: &str) -> IResult<&str, BodyParagraph> {
     )(input)
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct CopyrightFile {
     pub header_paragraph: HeaderParagraph,
     pub body_paragraphs: Vec<BodyParagraph>,
@@ -147,6 +138,226 @@ pub fn copyright_file(input: &str) -> IResult<&str, CopyrightFile> {
         },
     )(input)
 }
+
+impl CopyrightFile {
+    /// Finds the `Files:` paragraph that applies to `path` (relative to the
+    /// source root): DEP-5 specifies that when a path matches more than one
+    /// paragraph's glob patterns, the last matching paragraph wins.
+    pub fn resolve(&self, path: &str) -> Option<&FilesParagraph> {
+        self.body_paragraphs
+            .iter()
+            .filter_map(|p| match p {
+                BodyParagraph::Files(files_paragraph) if files_paragraph.files.matches(path) => {
+                    Some(files_paragraph)
+                }
+                _ => None,
+            })
+            .last()
+    }
+}
+
+/// A spanned variant of [`copyright_file`] for callers that want a located
+/// [`crate::span::ParseError`] (with a `context(...)` label stack) on
+/// failure, and field-level spans on success, instead of a bare nom error.
+pub mod spanned {
+    use nom::{
+        branch::alt,
+        character::complete::{line_ending, space0},
+        combinator::map,
+        error::{context, VerboseError, VerboseErrorKind},
+        multi::many0,
+        sequence::{delimited, pair, preceded},
+        IResult,
+    };
+
+    use crate::copyright_file::paragraph::spanned::{
+        paragraph as spanned_paragraph, SpannedParagraph,
+    };
+    use crate::span::{LineColumn, ParseError, SourceMap};
+
+    use super::fields::{Comment, Copyright, Files, Format, License};
+    use super::{CopyrightFile, FilesParagraph, HeaderParagraph, LicenseDetailParagraph};
+
+    type VResult<'a, O> = IResult<&'a str, O, VerboseError<&'a str>>;
+
+    /// A `context(...)`-labeled [`VerboseError`] pointing at `input`, for a
+    /// required field a [`SpannedParagraph`] turned out not to have.
+    fn missing_field<'a>(input: &'a str, message: &'static str) -> nom::Err<VerboseError<&'a str>> {
+        nom::Err::Failure(VerboseError {
+            errors: vec![(input, VerboseErrorKind::Context(message))],
+        })
+    }
+
+    fn header_paragraph<'a>(
+        source_map: &'a SourceMap<'a>,
+    ) -> impl FnMut(&'a str) -> VResult<'a, (HeaderParagraph, SpannedParagraph<'a>)> {
+        move |input| {
+            context("header paragraph", |input| {
+                let (rest, sp) = spanned_paragraph(source_map)(input)?;
+                let p = &sp.paragraph;
+                let format = p
+                    .extract::<Format>()
+                    .ok_or_else(|| missing_field(input, "missing Format field"))?;
+                let header = HeaderParagraph {
+                    format,
+                    upstream_name: p.extract(),
+                    upstream_contact: p.extract(),
+                    source: p.extract(),
+                    disclaimer: p.extract(),
+                    comment: p.extract::<Comment>(),
+                    license: p.extract::<License>(),
+                    copyright: p.extract::<Copyright>(),
+                };
+                Ok((rest, (header, sp)))
+            })(input)
+        }
+    }
+
+    fn files_paragraph<'a>(
+        source_map: &'a SourceMap<'a>,
+    ) -> impl FnMut(&'a str) -> VResult<'a, (FilesParagraph, SpannedParagraph<'a>)> {
+        move |input| {
+            context("files paragraph", |input| {
+                let (rest, sp) = spanned_paragraph(source_map)(input)?;
+                let p = &sp.paragraph;
+                // Without a `Files` field this isn't a files paragraph at all:
+                // report a recoverable error so `body_paragraph` goes on to try
+                // the license-detail alternative.
+                let files = p.extract::<Files>().ok_or_else(|| {
+                    nom::Err::Error(VerboseError {
+                        errors: vec![(input, VerboseErrorKind::Context("missing Files field"))],
+                    })
+                })?;
+                let copyright = p
+                    .extract::<Copyright>()
+                    .ok_or_else(|| missing_field(input, "missing Copyright field"))?;
+                let license =
+                    p.extract::<License>().ok_or_else(|| missing_field(input, "missing License field"))?;
+                let files_paragraph =
+                    FilesParagraph { files, copyright, license, comment: p.extract::<Comment>() };
+                Ok((rest, (files_paragraph, sp)))
+            })(input)
+        }
+    }
+
+    fn license_detail_paragraph<'a>(
+        source_map: &'a SourceMap<'a>,
+    ) -> impl FnMut(&'a str) -> VResult<'a, (LicenseDetailParagraph, SpannedParagraph<'a>)> {
+        move |input| {
+            context("license detail paragraph", |input| {
+                let (rest, sp) = spanned_paragraph(source_map)(input)?;
+                let license = sp
+                    .paragraph
+                    .extract::<License>()
+                    .ok_or_else(|| missing_field(input, "missing License field"))?;
+                let detail = LicenseDetailParagraph {
+                    name: license.name,
+                    text: license.text.unwrap_or_default(),
+                };
+                Ok((rest, (detail, sp)))
+            })(input)
+        }
+    }
+
+    enum VerboseBodyParagraph<'a> {
+        Files(FilesParagraph, SpannedParagraph<'a>),
+        LicenseDetail(LicenseDetailParagraph, SpannedParagraph<'a>),
+    }
+
+    fn body_paragraph<'a>(
+        source_map: &'a SourceMap<'a>,
+    ) -> impl FnMut(&'a str) -> VResult<'a, VerboseBodyParagraph<'a>> {
+        move |input| {
+            preceded(
+                many0(pair(space0, line_ending)),
+                alt((
+                    map(files_paragraph(source_map), |(p, sp)| {
+                        VerboseBodyParagraph::Files(p, sp)
+                    }),
+                    map(license_detail_paragraph(source_map), |(p, sp)| {
+                        VerboseBodyParagraph::LicenseDetail(p, sp)
+                    }),
+                )),
+            )(input)
+        }
+    }
+
+    /// A successfully parsed [`CopyrightFile`], alongside the raw,
+    /// span-resolved paragraphs (header first, then each body paragraph) it
+    /// was built from, in the same order as the file.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SpannedCopyrightFile<'a> {
+        pub file: CopyrightFile,
+        pub paragraphs: Vec<SpannedParagraph<'a>>,
+    }
+
+    /// A [`VerboseError`]-based variant of [`super::copyright_file`], built
+    /// on the same `context(...)`-labeled, span-resolving primitives as
+    /// [`crate::control_file::spanned`] and
+    /// [`crate::copyright_file::paragraph::spanned`], so a failure carries a
+    /// context stack all the way out instead of a bare position, and a
+    /// success carries every paragraph's field spans alongside it.
+    fn copyright_file_verbose<'a>(
+        source_map: &'a SourceMap<'a>,
+    ) -> impl FnMut(&'a str) -> VResult<'a, SpannedCopyrightFile<'a>> {
+        move |input| {
+            context(
+                "copyright file",
+                map(
+                    delimited(
+                        many0(pair(space0, line_ending)),
+                        pair(header_paragraph(source_map), many0(body_paragraph(source_map))),
+                        space0,
+                    ),
+                    |((header_paragraph, header_sp), body_paragraphs): (
+                        (HeaderParagraph, SpannedParagraph<'a>),
+                        Vec<VerboseBodyParagraph<'a>>,
+                    )| {
+                        let mut paragraphs = vec![header_sp];
+                        let body_paragraphs = body_paragraphs
+                            .into_iter()
+                            .map(|p| match p {
+                                VerboseBodyParagraph::Files(p, sp) => {
+                                    paragraphs.push(sp);
+                                    super::BodyParagraph::Files(p)
+                                }
+                                VerboseBodyParagraph::LicenseDetail(p, sp) => {
+                                    paragraphs.push(sp);
+                                    super::BodyParagraph::LicenseDetail(p)
+                                }
+                            })
+                            .collect();
+                        SpannedCopyrightFile {
+                            file: CopyrightFile { header_paragraph, body_paragraphs },
+                            paragraphs,
+                        }
+                    },
+                ),
+            )(input)
+        }
+    }
+
+    /// Parses `input` into a [`SpannedCopyrightFile`], resolving a parse
+    /// failure's position *and* its `context(...)` label stack against
+    /// `source_map` via [`ParseError::from_verbose`], rather than returning a
+    /// bare nom error, and attaching the span of every field in every
+    /// paragraph to a successful parse.
+    pub fn copyright_file_with_spans<'a>(
+        source_map: &'a SourceMap<'a>,
+        input: &'a str,
+    ) -> Result<SpannedCopyrightFile<'a>, ParseError> {
+        copyright_file_verbose(source_map)(input)
+            .map(|(_rest, file)| file)
+            .map_err(|err| match err {
+                nom::Err::Error(e) | nom::Err::Failure(e) => ParseError::from_verbose(source_map, &e),
+                nom::Err::Incomplete(_) => ParseError {
+                    location: LineColumn { line: 1, column: 1 },
+                    context: vec!["incomplete input"],
+                },
+            })
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -198,4 +409,68 @@ License: BSL-1.0
         )
         .expect("this is valid");
     }
+
+    #[test]
+    fn test_spanned_error_location() {
+        use super::spanned::copyright_file_with_spans;
+        use crate::span::SourceMap;
+
+        let input = "Format: https://example.com/\n\nFiles: *\nLicense: MIT\n";
+        let map = SourceMap::new(input);
+        let err = copyright_file_with_spans(&map, input)
+            .expect_err("missing Copyright field should fail to parse");
+        // the failure should be located somewhere past the header paragraph,
+        // not at the very start of the file.
+        assert!(err.location.line >= 3);
+        // and it should carry the context stack down to the failing paragraph,
+        // not just a bare position.
+        assert!(err.context.contains(&"files paragraph"));
+        assert!(err.context.contains(&"missing Copyright field"));
+    }
+
+    #[test]
+    fn test_spanned_success_attaches_field_spans() {
+        use super::spanned::copyright_file_with_spans;
+        use crate::span::SourceMap;
+
+        let input = "Format: https://example.com/\n\nFiles: *\nCopyright: 2021, Collabora, Ltd.\nLicense: MIT\n";
+        let map = SourceMap::new(input);
+        let spanned = copyright_file_with_spans(&map, input).expect("this is valid");
+
+        assert_eq!(spanned.file.header_paragraph.format.0, "https://example.com/\n");
+        // one paragraph per header/body paragraph, each carrying resolved field spans.
+        assert_eq!(spanned.paragraphs.len(), 2);
+        let files_fields: Vec<&str> = spanned.paragraphs[1]
+            .field_spans
+            .iter()
+            .map(|sf| sf.field.field_name)
+            .collect();
+        assert_eq!(files_fields, vec!["Files", "Copyright", "License"]);
+        let files_span = spanned.paragraphs[1].field_spans[0].name_span;
+        assert_eq!(&input[files_span.lo..files_span.hi], "Files");
+    }
+
+    #[test]
+    fn test_resolve_last_match_wins() {
+        use super::copyright_file;
+
+        let input = r#"
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+
+Files: *
+Copyright: 2021, Collabora, Ltd.
+License: MIT
+
+Files: src/special.rs
+Copyright: 2021, Someone Else
+License: Apache-2.0
+            "#;
+        let (_i, file) = copyright_file(input).expect("this is valid");
+
+        let generic = file.resolve("src/lib.rs").expect("matches the catch-all");
+        assert_eq!(generic.license.name, "MIT");
+
+        let special = file.resolve("src/special.rs").expect("matches both, last wins");
+        assert_eq!(special.license.name, "Apache-2.0");
+    }
 }
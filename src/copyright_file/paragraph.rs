@@ -0,0 +1,238 @@
+// Copyright 2021, Collabora, Ltd.
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! A generic, order-preserving deb822 paragraph model.
+//!
+//! A parser that hardcodes a fixed set of fields in fixed positions (e.g.
+//! via `permutation`) loses a real-world paragraph's extra, vendor, or
+//! reordered fields on a parse-edit-emit round trip. [`Paragraph`] instead parses a whole
+//! paragraph into an ordered list of raw [`Field`]s, preserving original
+//! field order and values, plus a case-insensitive lookup by name. The typed
+//! `HeaderParagraph`/`FilesParagraph` extraction in
+//! [`crate::copyright_file`] is layered on top of this model, so unknown
+//! fields survive a parse-edit-emit cycle.
+
+use std::collections::HashMap;
+
+use nom::{combinator::map, multi::many1, IResult};
+
+use crate::control_file::{field, Field, FieldName};
+use crate::copyright_file::fields::ParseField;
+use crate::error::CopyrightError;
+use crate::writer::WriteControl;
+
+/// An ordered, order-preserving deb822 paragraph: every field as parsed,
+/// in original order, with raw (un-cleaned) values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paragraph<'a> {
+    pub fields: Vec<Field<'a>>,
+}
+
+/// A field name that occurs more than once within a single paragraph, which
+/// is illegal in deb822. Surfaced as a recoverable diagnostic rather than
+/// silently keeping only the first (or last) occurrence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateField {
+    pub field_name: String,
+    pub occurrences: usize,
+}
+
+impl<'a> Paragraph<'a> {
+    /// Looks up the first field with this name, case-insensitively.
+    pub fn get(&self, name: &str) -> Option<&Field<'a>> {
+        self.fields
+            .iter()
+            .find(|f| f.field_name.eq_ignore_ascii_case(name))
+    }
+
+    /// Looks up every field with this name, case-insensitively, in original order.
+    pub fn get_all(&self, name: &str) -> Vec<&Field<'a>> {
+        self.fields
+            .iter()
+            .filter(|f| f.field_name.eq_ignore_ascii_case(name))
+            .collect()
+    }
+
+    /// Detects field names that occur more than once in this paragraph.
+    pub fn duplicate_fields(&self) -> Vec<DuplicateField> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for f in &self.fields {
+            *counts.entry(f.field_name.to_ascii_lowercase()).or_default() += 1;
+        }
+        let mut duplicates: Vec<_> = counts
+            .into_iter()
+            .filter(|(_, occurrences)| *occurrences > 1)
+            .map(|(field_name, occurrences)| DuplicateField {
+                field_name,
+                occurrences,
+            })
+            .collect();
+        duplicates.sort_by(|a, b| a.field_name.cmp(&b.field_name));
+        duplicates
+    }
+
+    /// Checks every field name against `known` (case-insensitively),
+    /// producing a [`CopyrightError::UnknownField`] for each one this
+    /// paragraph has that isn't in `known`, alongside a
+    /// [`CopyrightError::DuplicateField`] per name from
+    /// [`Self::duplicate_fields`].
+    pub fn validate(&self, known: &[&str]) -> Vec<CopyrightError> {
+        let mut errors: Vec<CopyrightError> = self
+            .fields
+            .iter()
+            .filter(|f| !known.iter().any(|k| f.field_name.eq_ignore_ascii_case(k)))
+            .map(|f| CopyrightError::UnknownField { field: f.field_name.to_string() })
+            .collect();
+        errors.extend(self.duplicate_fields().into_iter().map(CopyrightError::from));
+        errors
+    }
+
+    /// Looks up a typed field by parsing the raw field with the same name
+    /// back through `T`'s [`ParseField`] implementation. This is how the
+    /// typed paragraphs in [`crate::copyright_file`] are built on top of
+    /// this generic model.
+    pub fn extract<T: ParseField + FieldName>(&self) -> Option<T> {
+        let raw = self.get(T::NAME)?;
+        let mut text = String::new();
+        raw.write_to(&mut text);
+        T::parse(&text).ok().map(|(_rest, value)| value)
+    }
+}
+
+/// Parses a whole paragraph (a run of `field`s, ending at the first line
+/// that isn't a field, e.g. a blank line or EOF) into an order-preserving
+/// [`Paragraph`].
+pub fn paragraph(input: &str) -> IResult<&str, Paragraph<'_>> {
+    map(many1(field), |fields| Paragraph { fields })(input)
+}
+
+/// A [`VerboseError`](nom::error::VerboseError)-based variant of
+/// [`paragraph`], for callers that need the `context(...)` label stack and
+/// field-level spans a plain parse discards.
+pub mod spanned {
+    use nom::{
+        error::{context, VerboseError},
+        multi::many1,
+        IResult,
+    };
+
+    use crate::control_file::spanned::{field as spanned_field, SpannedField};
+    use crate::span::SourceMap;
+
+    use super::Paragraph;
+
+    type VResult<'a, O> = IResult<&'a str, O, VerboseError<&'a str>>;
+
+    /// A [`Paragraph`] together with the name/value spans of each of its fields.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SpannedParagraph<'a> {
+        pub paragraph: Paragraph<'a>,
+        pub field_spans: Vec<SpannedField<'a>>,
+    }
+
+    /// Parses a whole paragraph like [`super::paragraph`], additionally
+    /// resolving every field's spans against `source_map`, and running
+    /// against `VerboseError` so the `context(...)` labels added here and by
+    /// any caller layered on top of this survive to a failure. The
+    /// `"field name"` label on the underlying field-name parser is not
+    /// carried over.
+    pub fn paragraph<'a>(
+        source_map: &'a SourceMap<'a>,
+    ) -> impl FnMut(&'a str) -> VResult<'a, SpannedParagraph<'a>> {
+        move |input| {
+            context(
+                "paragraph",
+                nom::combinator::map(
+                    many1(spanned_field(source_map)),
+                    |field_spans: Vec<SpannedField<'a>>| {
+                        let paragraph = Paragraph {
+                            fields: field_spans.iter().map(|sf| sf.field).collect(),
+                        };
+                        SpannedParagraph { paragraph, field_spans }
+                    },
+                ),
+            )(input)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_spanned_paragraph_resolves_field_spans() {
+            let input = "Format: https://example.com/\nUpstream-Name: demo\n";
+            let map = SourceMap::new(input);
+            let (_i, sp) = paragraph(&map)(input).expect("parses");
+            assert_eq!(sp.paragraph.fields.len(), 2);
+            assert_eq!(sp.field_spans[0].field.field_name, "Format");
+            assert_eq!(sp.field_spans[0].name_span, crate::span::Span::new(0, 6));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::copyright_file::fields::Format;
+
+    #[test]
+    fn test_preserves_order_and_unknown_fields() {
+        let input = "Format: https://example.com/\nX-Vendor-Field: hello\nUpstream-Name: demo\n";
+        let (_i, p) = paragraph(input).expect("parses");
+        let names: Vec<_> = p.fields.iter().map(|f| f.field_name).collect();
+        assert_eq!(names, vec!["Format", "X-Vendor-Field", "Upstream-Name"]);
+    }
+
+    #[test]
+    fn test_case_insensitive_lookup() {
+        let input = "format: https://example.com/\n";
+        let (_i, p) = paragraph(input).expect("parses");
+        assert!(p.get("Format").is_some());
+    }
+
+    #[test]
+    fn test_duplicate_field_detection() {
+        let input = "Format: https://example.com/\nFormat: https://other.example/\n";
+        let (_i, p) = paragraph(input).expect("parses");
+        let dups = p.duplicate_fields();
+        assert_eq!(
+            dups,
+            vec![DuplicateField {
+                field_name: "format".to_string(),
+                occurrences: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn test_extract_typed_field() {
+        let input = "Format: https://example.com/\n";
+        let (_i, p) = paragraph(input).expect("parses");
+        let format: Format = p.extract().expect("Format field present");
+        assert_eq!(format.0, "https://example.com/\n");
+    }
+
+    #[test]
+    fn test_validate_flags_unknown_and_duplicate_fields() {
+        let input = "Format: https://example.com/\nFormat: https://other.example/\nX-Vendor: hi\n";
+        let (_i, p) = paragraph(input).expect("parses");
+        let errors = p.validate(&["Format"]);
+        assert_eq!(
+            errors,
+            vec![
+                CopyrightError::UnknownField { field: "X-Vendor".to_string() },
+                CopyrightError::DuplicateField { field: "format".to_string(), occurrences: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stops_at_blank_line() {
+        let input = "Format: https://example.com/\n\nFiles: *\n";
+        let (rest, p) = paragraph(input).expect("parses");
+        assert_eq!(p.fields.len(), 1);
+        assert_eq!(rest, "\nFiles: *\n");
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2021, Collabora, Ltd.
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! A typed error for diagnosing why a `debian/copyright` file is malformed,
+//! as an alternative to the bare `nom` errors the individual field parsers
+//! return internally.
+//!
+//! [`crate::copyright_file::fields::ParseField::parse`] and its neighbours
+//! stay nom-based, since that's what the combinators in
+//! [`crate::copyright_file`] and [`crate::copyright_file::paragraph`] need to
+//! keep composing them the usual way. [`CopyrightError`] is what a caller
+//! building a linter or other user-facing diagnostic should reach for
+//! instead, via the `_checked` parsers that map onto it.
+
+use std::fmt;
+
+/// Why a `debian/copyright` file, paragraph, or field failed to validate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CopyrightError {
+    /// A field that requires a value was present but empty, e.g. a bare
+    /// `License:` with nothing after it.
+    EmptyField { field: &'static str },
+    /// A field name this crate doesn't recognize, e.g. a typo'd `Licence:`.
+    UnknownField { field: String },
+    /// The same field name appeared more than once in a single paragraph.
+    DuplicateField { field: String, occurrences: usize },
+    /// A continuation line didn't match the fold/dot-encoding rules a
+    /// multi-line field expects.
+    MalformedContinuationLine { field: &'static str, line: String },
+    /// A `Copyright:` line didn't start with a recognized prefix (`©`,
+    /// `Copyright (c)`, or a bare year) and so couldn't be parsed at all.
+    UnrecognizedCopyrightPrefix { line: String },
+    /// A `Copyright:` line had a year or year range but no holder after it.
+    MissingHolderAfterYearRange { line: String },
+}
+
+impl fmt::Display for CopyrightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CopyrightError::EmptyField { field } => write!(f, "{field}: field is empty"),
+            CopyrightError::UnknownField { field } => {
+                write!(f, "{field}: unrecognized field name")
+            }
+            CopyrightError::DuplicateField { field, occurrences } => {
+                write!(f, "{field}: appears {occurrences} times in one paragraph")
+            }
+            CopyrightError::MalformedContinuationLine { field, line } => {
+                write!(f, "{field}: malformed continuation line: {line:?}")
+            }
+            CopyrightError::UnrecognizedCopyrightPrefix { line } => {
+                write!(f, "Copyright: unrecognized prefix: {line:?}")
+            }
+            CopyrightError::MissingHolderAfterYearRange { line } => {
+                write!(f, "Copyright: missing holder after year: {line:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CopyrightError {}
+
+impl From<crate::copyright_file::paragraph::DuplicateField> for CopyrightError {
+    fn from(dup: crate::copyright_file::paragraph::DuplicateField) -> Self {
+        CopyrightError::DuplicateField {
+            field: dup.field_name,
+            occurrences: dup.occurrences,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_messages() {
+        assert_eq!(
+            CopyrightError::EmptyField { field: "License" }.to_string(),
+            "License: field is empty"
+        );
+        assert_eq!(
+            CopyrightError::MissingHolderAfterYearRange { line: "2021".to_string() }.to_string(),
+            "Copyright: missing holder after year: \"2021\""
+        );
+    }
+
+    #[test]
+    fn test_from_duplicate_field() {
+        use crate::copyright_file::paragraph::DuplicateField;
+        let err: CopyrightError = DuplicateField {
+            field_name: "format".to_string(),
+            occurrences: 2,
+        }
+        .into();
+        assert_eq!(
+            err,
+            CopyrightError::DuplicateField { field: "format".to_string(), occurrences: 2 }
+        );
+    }
+}
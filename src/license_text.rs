@@ -0,0 +1,102 @@
+// Copyright 2021, Collabora, Ltd.
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Renders the canonical full text of common licenses from their SPDX short
+//! name, for writing (not just reading) a `debian/copyright` file.
+//!
+//! [`template_for`] looks up a bundled template by SPDX identifier; its
+//! `{{year}}`/`{{name}}` placeholders stand for the copyright year and
+//! holder respectively, and [`render`] fills them in. [`License::with_generated_text`](crate::copyright_file::fields::License::with_generated_text)
+//! is the intended entry point: it expands a `License { text: None, .. }`
+//! into one with `text: Some(rendered)`, so a generator can emit a
+//! spec-conformant file from just the SPDX identifiers the header scanner
+//! discovers.
+
+/// The bundled license templates, keyed by SPDX short identifier.
+const TEMPLATES: &[(&str, &str)] = &[
+    (
+        "MIT",
+        "Permission is hereby granted, free of charge, to any person obtaining a copy \
+of this software and associated documentation files (the \"Software\"), to deal \
+in the Software without restriction, including without limitation the rights \
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell \
+copies of the Software, and to permit persons to whom the Software is \
+furnished to do so, subject to the following conditions:\n\
+\n\
+The above copyright notice and this permission notice shall be included in all \
+copies or substantial portions of the Software.\n\
+\n\
+THE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR \
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, \
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE \
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER \
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, \
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE \
+SOFTWARE.",
+    ),
+    (
+        "0BSD",
+        "Permission to use, copy, modify, and/or distribute this software for any \
+purpose with or without fee is hereby granted.\n\
+\n\
+THE SOFTWARE IS PROVIDED \"AS IS\" AND THE AUTHOR DISCLAIMS ALL WARRANTIES WITH \
+REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF MERCHANTABILITY \
+AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR ANY SPECIAL, DIRECT, \
+INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES WHATSOEVER RESULTING FROM \
+LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION OF CONTRACT, NEGLIGENCE OR \
+OTHER TORTIOUS ACTION, ARISING OUT OF OR IN CONNECTION WITH THE USE OR \
+PERFORMANCE OF THIS SOFTWARE.",
+    ),
+    (
+        "CC0-1.0",
+        "{{name}} has waived all copyright and related or neighboring rights to this \
+work, to the extent possible under law, and dedicated it to the public domain. \
+See <https://creativecommons.org/publicdomain/zero/1.0/> for the full legal \
+text.",
+    ),
+];
+
+/// Looks up the bundled template for `license_id` (an SPDX short name like
+/// `MIT`), if this crate ships one.
+pub fn template_for(license_id: &str) -> Option<&'static str> {
+    TEMPLATES
+        .iter()
+        .find(|(id, _)| id.eq_ignore_ascii_case(license_id))
+        .map(|(_, text)| *text)
+}
+
+/// Fills `{{year}}` and `{{name}}` in `template` with `year` and `holder`.
+fn fill_placeholders(template: &str, year: &str, holder: &str) -> String {
+    template.replace("{{year}}", year).replace("{{name}}", holder)
+}
+
+/// Renders the canonical full text of `license_id` with `year` and `holder`
+/// substituted in, or `None` if no bundled template exists for it.
+pub fn render(license_id: &str, year: &str, holder: &str) -> Option<String> {
+    template_for(license_id).map(|template| fill_placeholders(template, year, holder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_lookup_is_case_insensitive() {
+        assert!(template_for("mit").is_some());
+        assert!(template_for("MIT").is_some());
+        assert!(template_for("Nonexistent-License").is_none());
+    }
+
+    #[test]
+    fn test_render_fills_placeholders() {
+        let rendered = render("CC0-1.0", "2021", "Collabora, Ltd.").expect("CC0-1.0 is bundled");
+        assert!(rendered.starts_with("Collabora, Ltd. has waived"));
+        assert!(!rendered.contains("{{"));
+    }
+
+    #[test]
+    fn test_render_unknown_license_returns_none() {
+        assert_eq!(render("Nonexistent-License", "2021", "Someone"), None);
+    }
+}
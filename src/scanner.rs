@@ -0,0 +1,287 @@
+// Copyright 2021, Collabora, Ltd.
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Harvests `SPDX-License-Identifier`/copyright comment headers from actual
+//! source files and synthesizes [`FilesParagraph`]s, closing the loop
+//! between in-code annotations and `debian/copyright`.
+//!
+//! A [`Language`] just carries the leading-comment prefix for a source
+//! dialect; [`read_header`] reads the leading comment block, skipping a
+//! first-line `#!` shebang and stopping at the first non-comment line.
+//! [`scan_files`] then groups files with an identical `(copyright, license)`
+//! pair into a single [`FilesParagraph`] whose `Files` globs list every path
+//! in the group. [`scan_tree`] wraps that with per-file language detection
+//! from the path's extension, and [`diff_against_declared`] compares a scan
+//! against an existing `debian/copyright` for compliance auditing.
+
+use std::collections::BTreeMap;
+
+use crate::copyright_file::fields::{parse_copyright_line, Copyright, Files, License};
+use crate::copyright_file::{CopyrightFile, FilesParagraph};
+
+/// A language's leading-comment convention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Language {
+    /// The prefix that marks a line as part of a comment header, e.g. `//` or `#`.
+    pub comment: &'static str,
+}
+
+impl Language {
+    pub const RUST: Language = Language { comment: "//" };
+    pub const C_FAMILY: Language = Language { comment: "//" };
+    pub const SHELL: Language = Language { comment: "#" };
+    pub const PYTHON: Language = Language { comment: "#" };
+
+    /// The default [`Language`] for a file extension (without the leading
+    /// `.`), or `None` if this crate doesn't know a default comment
+    /// convention for it.
+    pub fn for_extension(extension: &str) -> Option<Language> {
+        match extension {
+            "rs" => Some(Language::RUST),
+            "c" | "h" | "cc" | "cpp" | "hpp" | "cxx" | "java" | "js" | "ts" | "go" => {
+                Some(Language::C_FAMILY)
+            }
+            "sh" | "bash" => Some(Language::SHELL),
+            "py" => Some(Language::PYTHON),
+            _ => None,
+        }
+    }
+}
+
+/// The file extension (without the leading `.`) of a `/`-separated path, if any.
+fn extension_of(path: &str) -> Option<&str> {
+    path.rsplit('/').next()?.rsplit_once('.').map(|(_, ext)| ext)
+}
+
+/// Reads the leading comment-header block of `source`: skips a first-line
+/// `#!` shebang, then collects lines beginning with `lang.comment` (with
+/// that prefix, and up to one following space, stripped), stopping at the
+/// first line that isn't part of the header.
+pub fn read_header<'a>(lang: &Language, source: &'a str) -> Vec<&'a str> {
+    let mut lines = source.lines().peekable();
+    if lines.peek().is_some_and(|line| line.starts_with("#!")) {
+        lines.next();
+    }
+    let mut header = Vec::new();
+    for line in lines {
+        match line.strip_prefix(lang.comment) {
+            Some(rest) => header.push(rest.strip_prefix(' ').unwrap_or(rest)),
+            None => break,
+        }
+    }
+    header
+}
+
+/// Finds the `SPDX-License-Identifier:` line in a scanned header, if any.
+fn extract_spdx_license(header: &[&str]) -> Option<String> {
+    header.iter().find_map(|line| {
+        line.trim()
+            .strip_prefix("SPDX-License-Identifier:")
+            .map(|v| v.trim().to_string())
+    })
+}
+
+/// Strips a leading `Copyright` keyword, requiring it be followed by a
+/// non-alphanumeric character (or end of line), so `"Copyrighted material"`
+/// isn't mistaken for a notice.
+fn strip_copyright_keyword(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("Copyright")?;
+    match rest.chars().next() {
+        None => Some(rest),
+        Some(c) if !c.is_alphanumeric() => Some(rest),
+        _ => None,
+    }
+}
+
+/// Finds every `SPDX-FileCopyrightText:`/`Copyright`/`©` line in a scanned
+/// header, in order.
+fn extract_copyright_notices(header: &[&str]) -> Vec<String> {
+    header
+        .iter()
+        .filter_map(|line| {
+            let trimmed = line.trim();
+            let rest = trimmed
+                .strip_prefix("SPDX-FileCopyrightText:")
+                .or_else(|| trimmed.strip_prefix('©'))
+                .or_else(|| strip_copyright_keyword(trimmed))?;
+            let value = rest.trim_start_matches(':').trim().to_string();
+            (!value.is_empty()).then_some(value)
+        })
+        .collect()
+}
+
+/// Scans `(path, source)` pairs, reading each one's header with `lang`, and
+/// groups files with an identical `(copyright, license)` pair into one
+/// [`FilesParagraph`] per group, in deterministic order.
+pub fn scan_files<'a>(
+    lang: &Language,
+    files: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<FilesParagraph> {
+    let mut groups: BTreeMap<(Vec<String>, String), Vec<String>> = BTreeMap::new();
+    for (path, source) in files {
+        let header = read_header(lang, source);
+        let license = extract_spdx_license(&header).unwrap_or_default();
+        let copyright = extract_copyright_notices(&header);
+        groups
+            .entry((copyright, license))
+            .or_default()
+            .push(path.to_string());
+    }
+    groups
+        .into_iter()
+        .map(|((copyright, license), mut paths)| {
+            paths.sort();
+            FilesParagraph {
+                files: Files(paths),
+                copyright: Copyright(copyright.iter().map(|line| parse_copyright_line(line)).collect()),
+                license: License { name: license, text: None },
+                comment: None,
+            }
+        })
+        .collect()
+}
+
+/// Like [`scan_files`], but picks each file's [`Language`] from its
+/// extension via [`Language::for_extension`], silently skipping files whose
+/// extension has no known default. Grouping happens per extension: files
+/// with different extensions land in separate paragraphs even when their
+/// `(copyright, license)` pairs are identical.
+pub fn scan_tree<'a>(files: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<FilesParagraph> {
+    let recognized: Vec<(&str, &str)> = files
+        .into_iter()
+        .filter(|(path, _)| {
+            extension_of(path).is_some_and(|ext| Language::for_extension(ext).is_some())
+        })
+        .collect();
+
+    // Group per-language first, since each group's header must be read with
+    // its own comment convention, then flatten the resulting paragraphs.
+    let mut by_language: BTreeMap<&str, Vec<(&str, &str)>> = BTreeMap::new();
+    for (path, source) in recognized {
+        let ext = extension_of(path).expect("filtered above");
+        by_language.entry(ext).or_default().push((path, source));
+    }
+
+    by_language
+        .into_iter()
+        .flat_map(|(ext, files)| {
+            let lang = Language::for_extension(ext).expect("filtered above");
+            scan_files(&lang, files)
+        })
+        .collect()
+}
+
+/// Compares a fresh [`scan_files`]/[`scan_tree`] result against what
+/// `declared` already states for each scanned path, returning one
+/// human-readable line per path that is undeclared or whose declared
+/// license disagrees with what the source says.
+pub fn diff_against_declared(declared: &CopyrightFile, scanned: &[FilesParagraph]) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    for paragraph in scanned {
+        for path in &paragraph.files.0 {
+            match declared.resolve(path) {
+                Some(existing) if existing.license.name == paragraph.license.name => {}
+                Some(existing) => mismatches.push(format!(
+                    "{path}: declared license `{}` but source says `{}`",
+                    existing.license.name, paragraph.license.name
+                )),
+                None => mismatches.push(format!(
+                    "{path}: not declared in debian/copyright (source says `{}`)",
+                    paragraph.license.name
+                )),
+            }
+        }
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_header_rust() {
+        let source = "// Copyright 2021, Collabora, Ltd.\n//\n// SPDX-License-Identifier: MIT\n\nfn main() {}\n";
+        let header = read_header(&Language::RUST, source);
+        assert_eq!(
+            header,
+            vec!["Copyright 2021, Collabora, Ltd.", "", "SPDX-License-Identifier: MIT"]
+        );
+    }
+
+    #[test]
+    fn test_read_header_skips_shebang() {
+        let source = "#!/bin/sh\n# Copyright 2021, Collabora, Ltd.\n# SPDX-License-Identifier: MIT\necho hi\n";
+        let header = read_header(&Language::SHELL, source);
+        assert_eq!(
+            header,
+            vec!["Copyright 2021, Collabora, Ltd.", "SPDX-License-Identifier: MIT"]
+        );
+    }
+
+    #[test]
+    fn test_extract_copyright_notices_recognizes_bare_copyright_symbol() {
+        let header = ["© 2021, Collabora, Ltd.", "SPDX-License-Identifier: MIT"];
+        assert_eq!(extract_copyright_notices(&header), vec!["2021, Collabora, Ltd."]);
+    }
+
+    #[test]
+    fn test_extract_copyright_notices_rejects_copyrighted_as_a_word() {
+        let header = ["Copyrighted material, all rights reserved."];
+        assert!(extract_copyright_notices(&header).is_empty());
+    }
+
+    #[test]
+    fn test_scan_files_groups_by_copyright_and_license() {
+        let a = "// Copyright 2021, Collabora, Ltd.\n// SPDX-License-Identifier: MIT\n\nfn a() {}\n";
+        let b = "// Copyright 2021, Collabora, Ltd.\n// SPDX-License-Identifier: MIT\n\nfn b() {}\n";
+        let c = "// Copyright 2022, Someone Else\n// SPDX-License-Identifier: Apache-2.0\n\nfn c() {}\n";
+
+        let paragraphs = scan_files(&Language::RUST, [("a.rs", a), ("b.rs", b), ("c.rs", c)]);
+        assert_eq!(paragraphs.len(), 2);
+
+        let mit = paragraphs
+            .iter()
+            .find(|p| p.license.name == "MIT")
+            .expect("MIT group present");
+        assert_eq!(mit.files.0, vec!["a.rs", "b.rs"]);
+    }
+
+    #[test]
+    fn test_scan_tree_picks_language_by_extension() {
+        let rs = "// Copyright 2021, Collabora, Ltd.\n// SPDX-License-Identifier: MIT\n\nfn a() {}\n";
+        let sh = "#!/bin/sh\n# Copyright 2021, Collabora, Ltd.\n# SPDX-License-Identifier: MIT\necho hi\n";
+        let unknown = "whatever, no recognized extension";
+
+        let paragraphs = scan_tree([("a.rs", rs), ("b.sh", sh), ("c.xyz", unknown)]);
+        let all_files: Vec<&str> = paragraphs
+            .iter()
+            .flat_map(|p| p.files.0.iter().map(String::as_str))
+            .collect();
+        assert!(all_files.contains(&"a.rs"));
+        assert!(all_files.contains(&"b.sh"));
+        assert!(!all_files.contains(&"c.xyz"));
+    }
+
+    #[test]
+    fn test_diff_against_declared() {
+        use crate::copyright_file::copyright_file;
+
+        let declared = r#"
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+
+Files: *
+Copyright: 2021, Collabora, Ltd.
+License: Apache-2.0
+            "#;
+        let (_i, declared) = copyright_file(declared).expect("this is valid");
+
+        let rs = "// Copyright 2021, Collabora, Ltd.\n// SPDX-License-Identifier: MIT\n\nfn a() {}\n";
+        let scanned = scan_files(&Language::RUST, [("src/a.rs", rs)]);
+
+        let mismatches = diff_against_declared(&declared, &scanned);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("src/a.rs"));
+    }
+}
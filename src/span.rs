@@ -0,0 +1,196 @@
+// Copyright 2021, Collabora, Ltd.
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Byte-offset source locations for parse diagnostics.
+//!
+//! Modeled on proc-macro2's fallback `SourceMap`/`Cursor`: a [`SourceMap`] is
+//! built once from the original input, and every `&str` produced by the
+//! parsers in [`crate::control_file`] and [`crate::parser`] remains a
+//! sub-slice of that same buffer. Locating a sub-slice is then just pointer
+//! arithmetic against the map's base pointer, binary-searched against a
+//! table of line-start offsets.
+
+/// A byte-offset range into the original source buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Span {
+    pub lo: usize,
+    pub hi: usize,
+}
+
+impl Span {
+    pub fn new(lo: usize, hi: usize) -> Self {
+        Self { lo, hi }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hi - self.lo
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lo == self.hi
+    }
+}
+
+/// A 1-based line and column, as reported to a user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for LineColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Maps byte offsets in a source buffer back to 1-based line/column pairs.
+///
+/// Built once per input, from the base pointer of the original `&str` and a
+/// sorted table of line-start offsets (0, then the byte just after every
+/// `\n`). Any sub-slice of that same
+/// buffer can then be located by pointer arithmetic: `sub.as_ptr() as usize -
+/// base.as_ptr() as usize`.
+pub struct SourceMap<'a> {
+    base: &'a str,
+    // Byte offset of the start of each line, in ascending order. Always
+    // starts with 0, the start of the first line.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+    /// Builds a source map over `base`. Every sub-slice later looked up with
+    /// [`Self::offset_of`] or [`Self::span_of`] must be a sub-slice of `base`.
+    pub fn new(base: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(base.match_indices('\n').map(|(i, _)| i + 1));
+        Self { base, line_starts }
+    }
+
+    /// Computes the byte offset of `sub` within the buffer this map was built
+    /// over, via pointer arithmetic.
+    ///
+    /// Returns `None` if the computed offset would fall outside
+    /// `0..=self.base.len()`, which also catches `sub` not actually being a
+    /// sub-slice of the original buffer.
+    pub fn offset_of(&self, sub: &str) -> Option<usize> {
+        let base_ptr = self.base.as_ptr() as usize;
+        let sub_ptr = sub.as_ptr() as usize;
+        let offset = sub_ptr.checked_sub(base_ptr)?;
+        (offset <= self.base.len()).then_some(offset)
+    }
+
+    /// Resolves the span of a sub-slice of the original buffer.
+    pub fn span_of(&self, sub: &str) -> Option<Span> {
+        let lo = self.offset_of(sub)?;
+        let hi = lo.checked_add(sub.len())?;
+        (hi <= self.base.len()).then_some(Span::new(lo, hi))
+    }
+
+    /// Resolves a byte offset into a 1-based line and column, by
+    /// binary-searching the line-start table.
+    ///
+    /// Panics if `offset` is greater than the length of the original buffer.
+    pub fn location(&self, offset: usize) -> LineColumn {
+        assert!(offset <= self.base.len(), "offset out of bounds");
+        let line_idx = match self.line_starts.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        LineColumn {
+            line: line_idx + 1,
+            column: offset - self.line_starts[line_idx] + 1,
+        }
+    }
+
+    /// Resolves the start of a sub-slice of the original buffer into a 1-based
+    /// line and column.
+    pub fn location_of(&self, sub: &str) -> Option<LineColumn> {
+        self.offset_of(sub).map(|offset| self.location(offset))
+    }
+}
+
+/// A parse failure located in the original source, rendered as
+/// `line:col: context`, with `context` being the stack of nom
+/// `context(...)` labels collected on the way out of the parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub location: LineColumn,
+    pub context: Vec<&'static str>,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.location)?;
+        if self.context.is_empty() {
+            write!(f, "parse error")
+        } else {
+            write!(f, "{}", self.context.join(": "))
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    /// Builds a `ParseError` from a nom [`nom::error::VerboseError`], using
+    /// `map` to locate the innermost (deepest-matched) error position.
+    pub fn from_verbose(map: &SourceMap<'_>, err: &nom::error::VerboseError<&str>) -> Self {
+        let location = err
+            .errors
+            .first()
+            .and_then(|(input, _)| map.location_of(input))
+            .unwrap_or(LineColumn { line: 1, column: 1 });
+        let context = err
+            .errors
+            .iter()
+            .filter_map(|(_, kind)| match kind {
+                nom::error::VerboseErrorKind::Context(ctx) => Some(*ctx),
+                _ => None,
+            })
+            .collect();
+        Self { location, context }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_starts() {
+        let base = "abc\ndef\nghi";
+        let map = SourceMap::new(base);
+        assert_eq!(map.location(0), LineColumn { line: 1, column: 1 });
+        assert_eq!(map.location(4), LineColumn { line: 2, column: 1 });
+        assert_eq!(map.location(9), LineColumn { line: 3, column: 2 });
+    }
+
+    #[test]
+    fn test_span_of_sub_slice() {
+        let base = "abc\ndef\nghi";
+        let map = SourceMap::new(base);
+        let sub = &base[4..7];
+        assert_eq!(sub, "def");
+        assert_eq!(map.span_of(sub), Some(Span::new(4, 7)));
+        assert_eq!(map.location_of(sub), Some(LineColumn { line: 2, column: 1 }));
+    }
+
+    #[test]
+    fn test_foreign_slice_rejected() {
+        let base = "abc\ndef\nghi";
+        let map = SourceMap::new(base);
+        let other = String::from("not related");
+        assert_eq!(map.offset_of(&other), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let err = ParseError {
+            location: LineColumn { line: 4, column: 7 },
+            context: vec!["field name", "paragraph"],
+        };
+        assert_eq!(err.to_string(), "4:7: field name: paragraph");
+    }
+}
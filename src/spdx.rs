@@ -0,0 +1,197 @@
+// Copyright 2021, Collabora, Ltd.
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Parses SPDX license expressions, as used in DEP-5 `License` fields: e.g.
+//! `Apache-2.0 OR MIT`, `(GPL-2.0+ AND BSD-3-Clause)`, or
+//! `LGPL-2.1-only WITH exception`.
+//!
+//! This is a small nom precedence-climbing parser: `AND` binds tighter than
+//! `OR`, parentheses override grouping, a trailing `+` on a bare identifier
+//! means "or any later version", and `WITH` attaches an exception
+//! identifier to the operand on its left.
+
+use nom::{
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, multispace0, multispace1},
+    combinator::{map, opt},
+    multi::fold_many0,
+    sequence::{delimited, pair, preceded, tuple},
+    IResult,
+};
+
+/// An SPDX license expression, as a small AST.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseExpr {
+    /// A bare SPDX license (or exception) identifier, e.g. `MIT`.
+    Id(String),
+    /// `<expr>+`: the license, or any later version.
+    Plus(Box<LicenseExpr>),
+    /// `<expr> WITH <exception-id>`.
+    With(Box<LicenseExpr>, String),
+    /// `<expr> AND <expr>`.
+    And(Box<LicenseExpr>, Box<LicenseExpr>),
+    /// `<expr> OR <expr>`.
+    Or(Box<LicenseExpr>, Box<LicenseExpr>),
+}
+
+impl LicenseExpr {
+    /// Collects every distinct license/exception identifier referenced by
+    /// this expression, in the order they first appear.
+    pub fn license_ids(&self) -> Vec<&str> {
+        let mut ids = Vec::new();
+        self.collect_ids(&mut ids);
+        ids
+    }
+
+    fn collect_ids<'a>(&'a self, ids: &mut Vec<&'a str>) {
+        match self {
+            LicenseExpr::Id(id) => {
+                if !ids.contains(&id.as_str()) {
+                    ids.push(id.as_str());
+                }
+            }
+            LicenseExpr::Plus(inner) => inner.collect_ids(ids),
+            LicenseExpr::With(inner, exception) => {
+                inner.collect_ids(ids);
+                if !ids.contains(&exception.as_str()) {
+                    ids.push(exception.as_str());
+                }
+            }
+            LicenseExpr::And(lhs, rhs) | LicenseExpr::Or(lhs, rhs) => {
+                lhs.collect_ids(ids);
+                rhs.collect_ids(ids);
+            }
+        }
+    }
+}
+
+/// The SPDX short-identifier charset: `[A-Za-z0-9.+-]`. The trailing `+`, if
+/// any, is split off by [`id`] rather than kept as part of the identifier.
+fn identifier_token(input: &str) -> IResult<&str, &str> {
+    take_while1(|c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))(input)
+}
+
+/// A bare identifier, with a trailing `+` split off into [`LicenseExpr::Plus`].
+fn id(input: &str) -> IResult<&str, LicenseExpr> {
+    map(identifier_token, |token: &str| {
+        match token.strip_suffix('+') {
+            Some(base) => LicenseExpr::Plus(Box::new(LicenseExpr::Id(base.to_string()))),
+            None => LicenseExpr::Id(token.to_string()),
+        }
+    })(input)
+}
+
+/// `( license_expr )`, or a bare [`id`].
+fn atom(input: &str) -> IResult<&str, LicenseExpr> {
+    nom::branch::alt((
+        delimited(
+            char('('),
+            delimited(multispace0, license_expr, multispace0),
+            char(')'),
+        ),
+        id,
+    ))(input)
+}
+
+/// An atom, optionally followed by `WITH <exception-id>`.
+fn with_expr(input: &str) -> IResult<&str, LicenseExpr> {
+    map(
+        pair(
+            atom,
+            opt(preceded(
+                tuple((multispace1, tag("WITH"), multispace1)),
+                identifier_token,
+            )),
+        ),
+        |(expr, exception)| match exception {
+            Some(exception) => LicenseExpr::With(Box::new(expr), exception.to_string()),
+            None => expr,
+        },
+    )(input)
+}
+
+/// `with_expr`s joined by `AND`, left-associative; binds tighter than `OR`.
+fn and_expr(input: &str) -> IResult<&str, LicenseExpr> {
+    let (input, first) = with_expr(input)?;
+    fold_many0(
+        preceded(tuple((multispace1, tag("AND"), multispace1)), with_expr),
+        move || first.clone(),
+        |acc, next| LicenseExpr::And(Box::new(acc), Box::new(next)),
+    )(input)
+}
+
+/// `and_expr`s joined by `OR`, left-associative. The top-level entry point
+/// of this parser.
+pub fn license_expr(input: &str) -> IResult<&str, LicenseExpr> {
+    let (input, first) = and_expr(input)?;
+    fold_many0(
+        preceded(tuple((multispace1, tag("OR"), multispace1)), and_expr),
+        move || first.clone(),
+        |acc, next| LicenseExpr::Or(Box::new(acc), Box::new(next)),
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(s: &str) -> LicenseExpr {
+        LicenseExpr::Id(s.to_string())
+    }
+
+    #[test]
+    fn test_bare_id() {
+        let (i, o) = license_expr("MIT").expect("parses");
+        assert_eq!(o, id("MIT"));
+        assert!(i.is_empty());
+    }
+
+    #[test]
+    fn test_or() {
+        let (_i, o) = license_expr("Apache-2.0 OR MIT").expect("parses");
+        assert_eq!(o, LicenseExpr::Or(Box::new(id("Apache-2.0")), Box::new(id("MIT"))));
+    }
+
+    #[test]
+    fn test_and_binds_tighter_than_or() {
+        let (_i, o) = license_expr("MIT OR GPL-2.0 AND BSD-3-Clause").expect("parses");
+        assert_eq!(
+            o,
+            LicenseExpr::Or(
+                Box::new(id("MIT")),
+                Box::new(LicenseExpr::And(
+                    Box::new(id("GPL-2.0")),
+                    Box::new(id("BSD-3-Clause"))
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parens_override_grouping() {
+        let (_i, o) = license_expr("(GPL-2.0+ AND BSD-3-Clause)").expect("parses");
+        assert_eq!(
+            o,
+            LicenseExpr::And(
+                Box::new(LicenseExpr::Plus(Box::new(id("GPL-2.0")))),
+                Box::new(id("BSD-3-Clause"))
+            )
+        );
+    }
+
+    #[test]
+    fn test_with_exception() {
+        let (_i, o) = license_expr("LGPL-2.1-only WITH exception").expect("parses");
+        assert_eq!(
+            o,
+            LicenseExpr::With(Box::new(id("LGPL-2.1-only")), "exception".to_string())
+        );
+    }
+
+    #[test]
+    fn test_license_ids() {
+        let (_i, o) = license_expr("(GPL-2.0+ AND BSD-3-Clause) OR MIT").expect("parses");
+        assert_eq!(o.license_ids(), vec!["GPL-2.0", "BSD-3-Clause", "MIT"]);
+    }
+}
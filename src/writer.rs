@@ -0,0 +1,235 @@
+// Copyright 2021, Collabora, Ltd.
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Emits the parsed control/copyright structures back out as deb822 text.
+//!
+//! Mirrors, in reverse, the folding rules that [`crate::control_file::clean_multiline`]
+//! and its continuation-line parser already know how to read: a single-line
+//! field is written as `Name: value`; a multi-line field indents every
+//! continuation line by one space, and a blank line inside the value is
+//! written as a line containing only a lone `.`. Paragraphs are separated by
+//! a single blank line.
+
+use crate::control_file::{Field, FieldName};
+use crate::copyright_file::fields::{
+    Comment, Copyright, Disclaimer, Files, Format, License, Source, UpstreamContact, UpstreamName,
+};
+use crate::copyright_file::{
+    BodyParagraph, CopyrightFile, FilesParagraph, HeaderParagraph, LicenseDetailParagraph,
+};
+
+/// Implemented by anything that can render itself back out as deb822 text,
+/// by appending to an in-progress buffer.
+pub trait WriteControl {
+    fn write_to(&self, out: &mut String);
+}
+
+/// Writes `name: <first line>`, followed by every further line in `lines`
+/// indented by one space, turning an empty line into a lone `.` so the
+/// result parses back via `clean_multiline`.
+pub(crate) fn write_field_lines<'a>(
+    out: &mut String,
+    name: &str,
+    lines: impl IntoIterator<Item = &'a str>,
+) {
+    let mut lines = lines.into_iter();
+    out.push_str(name);
+    out.push_str(": ");
+    out.push_str(lines.next().unwrap_or(""));
+    out.push('\n');
+    for line in lines {
+        if line.is_empty() {
+            out.push_str(" .\n");
+        } else {
+            out.push(' ');
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+}
+
+impl<'a> WriteControl for Field<'a> {
+    /// The raw value is an unmodified sub-slice of the original source, so
+    /// this just re-attaches the field name and colon.
+    fn write_to(&self, out: &mut String) {
+        out.push_str(self.field_name);
+        out.push_str(": ");
+        out.push_str(self.value);
+        if !self.value.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+}
+
+/// Implements `WriteControl` for a single-field newtype whose `.0` is the
+/// raw, already-folded value text captured by `named_single_line_field` or
+/// `named_multi_line_field` (i.e. it already ends in its own line ending and
+/// already carries any continuation-line indentation and dot-markers).
+macro_rules! impl_write_control_for_raw_field {
+    ($($ty:ident),+ $(,)?) => {
+        $(
+            impl WriteControl for $ty {
+                fn write_to(&self, out: &mut String) {
+                    out.push_str(<$ty as FieldName>::NAME);
+                    out.push_str(": ");
+                    out.push_str(&self.0);
+                    if !self.0.ends_with('\n') {
+                        out.push('\n');
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_write_control_for_raw_field!(
+    Format,
+    UpstreamName,
+    UpstreamContact,
+    Source,
+    Disclaimer,
+    Comment
+);
+
+impl WriteControl for License {
+    fn write_to(&self, out: &mut String) {
+        // `text` keeps the source's trailing line ending; drop it before
+        // splitting so it doesn't turn into an extra ` .` line.
+        let lines = std::iter::once(self.name.as_str()).chain(
+            self.text
+                .as_deref()
+                .into_iter()
+                .flat_map(|text| text.strip_suffix('\n').unwrap_or(text).split('\n')),
+        );
+        write_field_lines(out, Self::NAME, lines);
+    }
+}
+
+impl WriteControl for Copyright {
+    fn write_to(&self, out: &mut String) {
+        let rendered: Vec<String> = self.0.iter().map(|line| line.render()).collect();
+        write_field_lines(out, Self::NAME, rendered.iter().map(String::as_str));
+    }
+}
+
+impl WriteControl for Files {
+    fn write_to(&self, out: &mut String) {
+        write_field_lines(out, Self::NAME, self.0.iter().map(String::as_str));
+    }
+}
+
+impl WriteControl for HeaderParagraph {
+    fn write_to(&self, out: &mut String) {
+        self.format.write_to(out);
+        if let Some(v) = &self.upstream_name {
+            v.write_to(out);
+        }
+        if let Some(v) = &self.upstream_contact {
+            v.write_to(out);
+        }
+        if let Some(v) = &self.source {
+            v.write_to(out);
+        }
+        if let Some(v) = &self.disclaimer {
+            v.write_to(out);
+        }
+        if let Some(v) = &self.comment {
+            v.write_to(out);
+        }
+        if let Some(v) = &self.license {
+            v.write_to(out);
+        }
+        if let Some(v) = &self.copyright {
+            v.write_to(out);
+        }
+    }
+}
+
+impl WriteControl for FilesParagraph {
+    fn write_to(&self, out: &mut String) {
+        self.files.write_to(out);
+        self.copyright.write_to(out);
+        self.license.write_to(out);
+        if let Some(v) = &self.comment {
+            v.write_to(out);
+        }
+    }
+}
+
+impl WriteControl for LicenseDetailParagraph {
+    fn write_to(&self, out: &mut String) {
+        if self.text.is_empty() {
+            write_field_lines(out, "License", std::iter::once(self.name.as_str()));
+        } else {
+            let lines = std::iter::once(self.name.as_str()).chain(self.text.split('\n'));
+            write_field_lines(out, "License", lines);
+        }
+    }
+}
+
+impl WriteControl for BodyParagraph {
+    fn write_to(&self, out: &mut String) {
+        match self {
+            BodyParagraph::Files(p) => p.write_to(out),
+            BodyParagraph::LicenseDetail(p) => p.write_to(out),
+        }
+    }
+}
+
+impl WriteControl for CopyrightFile {
+    fn write_to(&self, out: &mut String) {
+        self.header_paragraph.write_to(out);
+        for paragraph in &self.body_paragraphs {
+            out.push('\n');
+            paragraph.write_to(out);
+        }
+    }
+}
+
+impl std::fmt::Display for CopyrightFile {
+    /// Renders this file back out as deb822 text.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut out = String::new();
+        self.write_to(&mut out);
+        f.write_str(&out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WriteControl;
+    use crate::copyright_file::copyright_file;
+
+    const SAMPLE: &str = r#"Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+
+Files: CONTRIBUTING.md
+Copyright: 2018-2019 Collabora, Ltd.
+    and License for this CONTRIBUTING.md file
+License: CC-BY-4.0
+
+Files: README.md
+Copyright: 2018-2020, Collabora, Ltd.
+License: CC-BY-4.0
+"#;
+
+    #[test]
+    fn test_round_trip() {
+        let (_i, parsed) = copyright_file(SAMPLE).expect("sample parses");
+        let emitted = parsed.to_string();
+        let (_i, reparsed) = copyright_file(&emitted).expect("emitted text reparses");
+        assert_eq!(parsed, reparsed);
+    }
+
+    #[test]
+    fn test_write_field() {
+        use crate::control_file::Field;
+        let f = Field {
+            field_name: "Format",
+            value: "https://example.com/\n",
+        };
+        let mut out = String::new();
+        f.write_to(&mut out);
+        assert_eq!(out, "Format: https://example.com/\n");
+    }
+}